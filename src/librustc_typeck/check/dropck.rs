@@ -6,8 +6,7 @@ use crate::util::common::ErrorReported;
 use rustc::infer::outlives::env::OutlivesEnvironment;
 use rustc::infer::{InferOk, SuppressRegionErrors};
 use rustc::middle::region;
-use rustc::traits::{ObligationCause, TraitEngine, TraitEngineExt};
-use rustc::ty::relate::{Relate, RelateResult, TypeRelation};
+use rustc::traits::{self, Obligation, ObligationCause, Reveal, TraitEngine, TraitEngineExt};
 use rustc::ty::subst::{Subst, SubstsRef};
 use rustc::ty::{self, Predicate, Ty, TyCtxt};
 
@@ -22,9 +21,10 @@ use syntax_pos::Span;
 /// 1. The self type must be nominal (this is already checked during
 ///    coherence),
 ///
-/// 2. The generic region/type parameters of the impl's self type must
-///    all be parameters of the Drop impl itself (i.e., no
-///    specialization like `impl Drop for Foo<i32>`), and,
+/// 2. The generic region/type/const parameters of the impl's self type
+///    must all be parameters of the Drop impl itself (i.e., no
+///    specialization like `impl Drop for Foo<i32>` or
+///    `impl Drop for Arr<3>`), and,
 ///
 /// 3. Any bounds on the generic parameters must be reflected in the
 ///    struct/enum definition for the nominal type itself (i.e.
@@ -100,7 +100,7 @@ fn ensure_drop_params_and_item_params_correspond<'tcx>(
                 )
                 .span_note(
                     item_span,
-                    "Use same sequence of generic type and region \
+                    "Use same sequence of generic type, const, and region \
                      parameters that is on the struct/enum definition",
                 )
                 .emit();
@@ -183,8 +183,10 @@ fn ensure_drop_predicates_are_implied_by_item_defn<'tcx>(
     // predicate that is not present on the struct definition.
 
     let self_type_hir_id = tcx.hir().as_local_hir_id(self_type_did).unwrap();
+    let drop_impl_hir_id = tcx.hir().as_local_hir_id(drop_impl_did).unwrap();
 
     let drop_impl_span = tcx.def_span(drop_impl_did);
+    let item_span = tcx.hir().span(self_type_hir_id);
 
     // We can assume the predicates attached to struct/enum definition
     // hold.
@@ -193,83 +195,208 @@ fn ensure_drop_predicates_are_implied_by_item_defn<'tcx>(
     let assumptions_in_impl_context = generic_assumptions.instantiate(tcx, &self_to_impl_substs);
     let assumptions_in_impl_context = assumptions_in_impl_context.predicates;
 
-    // An earlier version of this code attempted to do this checking
-    // via the traits::fulfill machinery. However, it ran into trouble
-    // since the fulfill machinery merely turns outlives-predicates
-    // 'a:'b and T:'b into region inference constraints. It is simpler
-    // just to look for all the predicates directly.
-
-    ///////////////////////////////
-    let self_param_env = tcx.param_env(self_type_did);
+    // Reports E0367: the Drop impl injected a requirement that the
+    // struct/enum definition does not make.
+    let report_unimplied = |predicate: &Predicate<'_>| {
+        struct_span_err!(
+            tcx.sess,
+            drop_impl_span,
+            E0367,
+            "The requirement `{}` is added only by the Drop impl.",
+            predicate
+        )
+        .span_note(
+            item_span,
+            "The same requirement must be part of \
+             the struct/enum definition",
+        )
+        .emit();
+    };
+
+    // (We do not need to worry about deep analysis of type expressions
+    // etc because the Drop impls are already forced to take on a
+    // structure that is roughly an alpha-renaming of the generic
+    // parameters of the item definition.)
 
     assert_eq!(dtor_predicates.parent, None);
-    for (predicate, _) in dtor_predicates.predicates {
-        // (We do not need to worry about deep analysis of type
-        // expressions etc because the Drop impls are already forced
-        // to take on a structure that is roughly an alpha-renaming of
-        // the generic parameters of the item definition.)
-
-        // This path now just checks *all* predicates via the direct
-        // lookup, rather than using fulfill machinery.
-        //
-        // However, it may be more efficient in the future to batch
-        // the analysis together via the fulfill , rather than the
-        // repeated `contains` calls.
-
-        if !assumptions_in_impl_context.iter().any(|p: &'_ Predicate<'_>| {
-            let mut relator = Relator::new(tcx, self_param_env);
-            predicate_matches(predicate, p, &mut relator)
-        }) {
-            let item_span = tcx.hir().span(self_type_hir_id);
-            struct_span_err!(
-                tcx.sess,
-                drop_impl_span,
-                E0367,
-                "The requirement `{}` is added only by the Drop impl.",
-                predicate
-            )
-            .span_note(
-                item_span,
-                "The same requirement must be part of \
-                 the struct/enum definition",
-            )
-            .emit();
+
+    // Trait, projection and const-evaluatable predicates are batched together
+    // and discharged in a single run of the fulfillment engine, with the
+    // instantiated item assumptions supplied as the caller bounds. Routing them
+    // through the trait solver means supertraits and otherwise
+    // transitively-implied bounds -- including `where`-clause bounds that
+    // mention the impl's const parameters -- are recognized as satisfying a
+    // Drop-impl predicate, rather than demanding a syntactically equal
+    // assumption.
+    //
+    // Outlives predicates deliberately do *not* go through fulfill: the engine
+    // merely lowers `'a: 'b` and `T: 'b` to region-inference constraints, so it
+    // would silently accept an impl that adds fresh region requirements. Those
+    // we check against the transitive closure of the outlives relation entailed
+    // by the item assumptions, so that a bound such as `'c: 'a` is accepted when
+    // the struct only declares `'b: 'a` and `'c: 'b`, while any requirement that
+    // is *not* entailed by the closure still produces E0367.
+    // The caller bounds are elaborated (supertraits included), since trait
+    // selection only matches a caller bound that is literally present.
+    let elaborated_assumptions: Vec<_> =
+        traits::elaborate_predicates(tcx, assumptions_in_impl_context.clone()).collect();
+    let impl_assumptions =
+        ty::ParamEnv::new(tcx.intern_predicates(&elaborated_assumptions), Reveal::UserFacing);
+
+    let region_relation = RegionRelation::new(&assumptions_in_impl_context);
+
+    tcx.infer_ctxt().enter(|ref infcx| {
+        let mut fulfillment_cx = TraitEngine::new(tcx);
+
+        // Remember, keyed by the span each obligation is registered with, the
+        // `dtor_predicates` entry it came from. A fulfillment error reports
+        // whatever derived predicate the engine stalled on, but its cause span
+        // is threaded down from the root obligation, so this lets us map the
+        // error back and quote the bound the user actually wrote on the impl.
+        let mut origins: Vec<(Span, &Predicate<'tcx>)> = Vec::new();
+
+        for (predicate, span) in dtor_predicates.predicates {
+            match predicate {
+                Predicate::Trait(..)
+                | Predicate::Projection(..)
+                | Predicate::ConstEvaluatable(..) => {
+                    let cause = ObligationCause::misc(*span, drop_impl_hir_id);
+                    let obligation =
+                        Obligation::new(cause, impl_assumptions, predicate.clone());
+                    fulfillment_cx.register_predicate_obligation(infcx, obligation);
+                    origins.push((*span, predicate));
+                }
+
+                Predicate::RegionOutlives(binder) => {
+                    let &ty::OutlivesPredicate(sup, sub) = binder.skip_binder();
+                    if !region_relation.region_outlives_region(sup, sub) {
+                        report_unimplied(predicate);
+                        result = Err(ErrorReported);
+                    }
+                }
+
+                Predicate::TypeOutlives(binder) => {
+                    let &ty::OutlivesPredicate(ty, region) = binder.skip_binder();
+                    if !region_relation.type_outlives_region(ty, region) {
+                        report_unimplied(predicate);
+                        result = Err(ErrorReported);
+                    }
+                }
+
+                _ => {
+                    // Any remaining predicate kind is matched structurally
+                    // against the instantiated assumptions.
+                    if !assumptions_in_impl_context.contains(predicate) {
+                        report_unimplied(predicate);
+                        result = Err(ErrorReported);
+                    }
+                }
+            }
+        }
+
+        // A single solve over every batched trait/projection obligation; each
+        // leftover fulfillment error is mapped back, through its cause span, to
+        // the originating `dtor_predicates` entry so the diagnostic quotes the
+        // bound written on the impl rather than a derived obligation.
+        if let Err(ref errors) = fulfillment_cx.select_all_or_error(infcx) {
+            for error in errors {
+                let origin = origins
+                    .iter()
+                    .find(|(span, _)| *span == error.obligation.cause.span)
+                    .map(|&(_, predicate)| predicate)
+                    .unwrap_or(&error.obligation.predicate);
+                report_unimplied(origin);
+            }
             result = Err(ErrorReported);
         }
-    }
+    });
 
     result
 }
 
-fn predicate_matches<'a>(
-    p1: &'_ Predicate<'a>,
-    p2: &'_ Predicate<'a>,
-    relator: &mut Relator<'a>,
-) -> bool {
-    // let combine_fields = CombineFields {
-    //     infcx: infer_ctx,
-    //     trace: TypeTrace::dummy(tcx),
-    //     cause: None,
-    //     self_param_env,
-    //     obligations: PredicateObligations::new(),
-    // };
-    match (p1, p2) {
-        (Predicate::Trait(a), Predicate::Trait(b)) => relate_predicates(relator, a, b),
-        (Predicate::Projection(a), Predicate::Projection(b)) => relate_predicates(relator, a, b),
-        _ => p1 == p2,
-    }
+/// The transitive closure of the outlives relation entailed by a set of
+/// (already instantiated) item assumptions, used to decide whether an outlives
+/// predicate imposed by a Drop impl is implied by the struct/enum definition.
+///
+/// Region-region assumptions `'x: 'y` are read as directed edges `'x -> 'y` and
+/// closed under transitivity (a Floyd–Warshall-style fixpoint over the finite
+/// set of named impl regions), seeded with reflexivity. `'static` is treated as
+/// dominating every region. Type-region assumptions `T: 'x` are kept alongside
+/// so that `T: 'y` can be discharged whenever `'x: 'y` is in the closure.
+struct RegionRelation<'tcx> {
+    region_outlives: Vec<(ty::Region<'tcx>, ty::Region<'tcx>)>,
+    type_outlives: Vec<(Ty<'tcx>, ty::Region<'tcx>)>,
 }
 
-fn relate_predicates<T: Relate<'a>>(relator: &mut Relator<'a>, a: &T, b: &T) -> bool {
-    match relator.relate(a, b) {
-        Ok(v) => {
-            debug!("Ok(value) - {:?}", v);
-            true
+impl<'tcx> RegionRelation<'tcx> {
+    fn new(assumptions: &[Predicate<'tcx>]) -> RegionRelation<'tcx> {
+        let mut region_outlives = Vec::new();
+        let mut type_outlives = Vec::new();
+        let mut regions: Vec<ty::Region<'tcx>> = Vec::new();
+
+        let note_region = |r: ty::Region<'tcx>, regions: &mut Vec<_>| {
+            if !regions.contains(&r) {
+                regions.push(r);
+            }
+        };
+
+        for predicate in assumptions {
+            match predicate {
+                Predicate::RegionOutlives(binder) => {
+                    let &ty::OutlivesPredicate(sup, sub) = binder.skip_binder();
+                    note_region(sup, &mut regions);
+                    note_region(sub, &mut regions);
+                    region_outlives.push((sup, sub));
+                }
+                Predicate::TypeOutlives(binder) => {
+                    let &ty::OutlivesPredicate(ty, region) = binder.skip_binder();
+                    note_region(region, &mut regions);
+                    type_outlives.push((ty, region));
+                }
+                _ => {}
+            }
         }
-        Err(e) => {
-            debug!("Err(e) - {:?}", e);
-            false
+
+        // Close the region relation under transitivity over the finite set of
+        // named regions. `'r: 'r` and `'static: 'r` are handled at query time.
+        loop {
+            let mut new_edges = Vec::new();
+            for &a in &regions {
+                for &b in &regions {
+                    for &c in &regions {
+                        let ab = a == b || region_outlives.contains(&(a, b));
+                        let bc = b == c || region_outlives.contains(&(b, c));
+                        let ac = a == c || region_outlives.contains(&(a, c));
+                        if ab && bc && !ac && !new_edges.contains(&(a, c)) {
+                            new_edges.push((a, c));
+                        }
+                    }
+                }
+            }
+            if new_edges.is_empty() {
+                break;
+            }
+            region_outlives.extend(new_edges);
         }
+
+        RegionRelation { region_outlives, type_outlives }
+    }
+
+    /// Is `sup: sub` entailed by the closure? `'static` outlives every region
+    /// and every region outlives itself.
+    fn region_outlives_region(&self, sup: ty::Region<'tcx>, sub: ty::Region<'tcx>) -> bool {
+        let sup_is_static = if let ty::ReStatic = sup { true } else { false };
+        sup == sub || sup_is_static || self.region_outlives.contains(&(sup, sub))
+    }
+
+    /// Is `ty: region` entailed? We have it whenever the definition assumes
+    /// `ty: 'x` for some `'x` that outlives `region` in the closure.
+    fn type_outlives_region(&self, ty: Ty<'tcx>, region: ty::Region<'tcx>) -> bool {
+        self.type_outlives
+            .iter()
+            .any(|&(assumed_ty, assumed_region)| {
+                assumed_ty == ty && self.region_outlives_region(assumed_region, region)
+            })
     }
 }
 
@@ -291,101 +418,3 @@ crate fn check_drop_obligations<'a, 'tcx>(
 
     Ok(())
 }
-
-crate struct Relator<'tcx> {
-    tcx: TyCtxt<'tcx>,
-    param_env: ty::ParamEnv<'tcx>,
-}
-
-impl<'tcx> Relator<'tcx> {
-    fn new(tcx: TyCtxt<'tcx>, param_env: ty::ParamEnv<'tcx>) -> Relator<'tcx> {
-        Relator { tcx, param_env }
-    }
-}
-
-impl TypeRelation<'tcx> for Relator<'tcx> {
-    fn tcx(&self) -> TyCtxt<'tcx> {
-        self.tcx
-    }
-
-    fn param_env(&self) -> ty::ParamEnv<'tcx> {
-        self.param_env
-    }
-
-    fn tag(&self) -> &'static str {
-        "dropck::Relator"
-    }
-
-    fn a_is_expected(&self) -> bool {
-        true
-    }
-
-    fn relate_with_variance<T: Relate<'tcx>>(
-        &mut self,
-        _: ty::Variance,
-        a: &T,
-        b: &T,
-    ) -> RelateResult<'tcx, T> {
-        self.relate(a, b)
-    }
-
-    fn tys(&mut self, a: Ty<'tcx>, b: Ty<'tcx>) -> RelateResult<'tcx, Ty<'tcx>> {
-        match (&a.kind, &b.kind) {
-            (_, &ty::Infer(_)) | (&ty::Infer(_), _) => {
-                // Forbid inference variables during the dropck.
-                bug!("unexpected inference var {:?}", b)
-            }
-
-            _ => {
-                debug!("tys(a={:?}, b={:?})", a, b);
-
-                // Will also handle unification of `IntVar` and `FloatVar`.
-                self.tcx.infer_ctxt().enter(|infcx| infcx.super_combine_tys(self, a, b))
-            }
-        }
-    }
-
-    fn regions(
-        &mut self,
-        a: ty::Region<'tcx>,
-        b: ty::Region<'tcx>,
-    ) -> RelateResult<'tcx, ty::Region<'tcx>> {
-        debug!("regions(a={:?}, b={:?})", a, b);
-
-        Ok(a)
-    }
-
-    fn consts(
-        &mut self,
-        a: &'tcx ty::Const<'tcx>,
-        b: &'tcx ty::Const<'tcx>,
-    ) -> RelateResult<'tcx, &'tcx ty::Const<'tcx>> {
-        match (a.val, b.val) {
-            // (ty::ConstKind::Infer(_), _) => {
-            //     // Forbid inference variables.
-            //     bug!("unexpected inference var {:?}", a)
-            // }
-
-            // (_, ty::ConstKind::Infer(_)) => {
-            //     // Forbid inference variables.
-            //     bug!("unexpected inference var {:?}", b)
-            // }
-            _ => self.tcx.infer_ctxt().enter(|infcx| infcx.super_combine_consts(self, a, b)),
-        }
-    }
-
-    fn binders<T>(
-        &mut self,
-        a: &ty::Binder<T>,
-        b: &ty::Binder<T>,
-    ) -> RelateResult<'tcx, ty::Binder<T>>
-    where
-        T: Relate<'tcx>,
-    {
-        debug!("binders({:?}: {:?}", a, b);
-
-        self.relate(a.skip_binder(), b.skip_binder())?;
-
-        Ok(a.clone())
-    }
-}
@@ -0,0 +1,13 @@
+//! A Drop impl that fixes the `const` parameter of its self type to a concrete
+//! value is a specialization and is rejected, just like `impl Drop for Foo<i32>`.
+#![feature(const_generics)]
+#![allow(incomplete_features)]
+
+#[allow(dead_code)]
+struct Arr<const N: usize>([u8; N]);
+
+impl Drop for Arr<4> { //~ ERROR E0366
+    fn drop(&mut self) {}
+}
+
+fn main() {}
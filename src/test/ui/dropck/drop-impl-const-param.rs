@@ -0,0 +1,14 @@
+// run-pass
+//! A Drop impl whose self type is generic over a bare `const` parameter is
+//! accepted, on the same footing as type and lifetime parameters.
+#![feature(const_generics)]
+#![allow(incomplete_features)]
+
+#[allow(dead_code)]
+struct Arr<const N: usize>([u8; N]);
+
+impl<const N: usize> Drop for Arr<N> {
+    fn drop(&mut self) {}
+}
+
+fn main() {}
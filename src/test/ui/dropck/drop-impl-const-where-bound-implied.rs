@@ -0,0 +1,23 @@
+// run-pass
+//! chunk0-2: a `where`-clause bound mentioning the impl's const parameter is
+//! accepted when it is satisfiable from the item context -- here `Arr<N>: Bound`
+//! holds for all `N` via the blanket impl, so the check (which runs through the
+//! fulfillment engine) discharges it.
+#![feature(const_generics)]
+#![allow(incomplete_features)]
+
+trait Bound {}
+
+#[allow(dead_code)]
+struct Arr<const N: usize>([u8; N]);
+
+impl<const N: usize> Bound for Arr<N> {}
+
+impl<const N: usize> Drop for Arr<N>
+where
+    Arr<N>: Bound,
+{
+    fn drop(&mut self) {}
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+//! chunk0-2: a `where`-clause bound on a const-generic Drop impl that is not
+//! implied by the struct definition is rejected with E0367.
+#![feature(const_generics)]
+#![allow(incomplete_features)]
+
+trait Bound {}
+
+#[allow(dead_code)]
+struct Arr<const N: usize>([u8; N]);
+
+impl<const N: usize> Drop for Arr<N> where Arr<N>: Bound { //~ ERROR E0367
+    fn drop(&mut self) {}
+}
+
+fn main() {}
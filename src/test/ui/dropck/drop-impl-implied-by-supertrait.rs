@@ -0,0 +1,21 @@
+// run-pass
+//! The implied-predicate check for Drop impls goes through the trait solver,
+//! so a trait bound on the impl that is only *transitively* provided by the
+//! struct's own bound (here, via a supertrait) is accepted rather than
+//! requiring a syntactically identical bound on the definition.
+
+trait Foo {}
+trait Bar: Foo {}
+
+#[allow(dead_code)]
+struct S<T: Bar>(T);
+
+// `T: Bar` (declared on `S`) implies `T: Foo`, so this extra bound is sound.
+impl<T: Bar> Drop for S<T>
+where
+    T: Foo,
+{
+    fn drop(&mut self) {}
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+// run-pass
+//! An outlives bound on a Drop impl that is only *transitively* entailed by the
+//! struct's declared bounds is accepted: `S` provides `'b: 'a` and `'c: 'b`, so
+//! the impl's `'c: 'a` is sound even though it is not written on the struct.
+
+#[allow(dead_code)]
+struct S<'a, 'b: 'a, 'c: 'b> {
+    x: &'a u8,
+    y: &'b u8,
+    z: &'c u8,
+}
+
+impl<'a, 'b: 'a, 'c: 'b> Drop for S<'a, 'b, 'c>
+where
+    'c: 'a,
+{
+    fn drop(&mut self) {}
+}
+
+fn main() {}